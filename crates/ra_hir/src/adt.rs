@@ -1,14 +1,17 @@
 use std::sync::Arc;
 
-use ra_syntax::{SmolStr, ast::{self, NameOwner, StructFlavor}};
+use ra_syntax::{ast::{self, NameOwner, StructFlavor}};
 
 use crate::{
     DefId, Cancelable,
     db::{HirDatabase},
     module::Module,
-    ty::{Ty},
+    name::{Name, AsName},
+    ty::Ty,
+    type_ref::TypeRef,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Struct {
     def_id: DefId,
 }
@@ -30,30 +33,38 @@ impl Struct {
         Ok(db.struct_data(self.def_id)?)
     }
 
-    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<SmolStr>> {
+    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<Name>> {
         Ok(db.struct_data(self.def_id)?.name.clone())
     }
+
+    /// Resolves `name` to a field of this struct, be it a named field or a
+    /// numeric tuple index (e.g. `foo.bar` or `foo.0`).
+    pub fn field(&self, db: &impl HirDatabase, name: &Name) -> Cancelable<Option<StructField>> {
+        let data = self.variant_data(db)?;
+        let owner = VariantId::Struct(*self);
+        Ok(data.field(name).map(|idx| StructField::new(owner, idx)))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StructData {
-    name: Option<SmolStr>,
+    name: Option<Name>,
     variant_data: Arc<VariantData>,
 }
 
 impl StructData {
     pub(crate) fn new(
-        db: &impl HirDatabase,
-        module: &Module,
+        _db: &impl HirDatabase,
+        _module: &Module,
         struct_def: ast::StructDef,
     ) -> Cancelable<StructData> {
-        let name = struct_def.name().map(|n| n.text());
-        let variant_data = VariantData::new(db, module, struct_def.flavor())?;
+        let name = struct_def.name().map(|n| n.as_name());
+        let variant_data = VariantData::new(struct_def.flavor());
         let variant_data = Arc::new(variant_data);
         Ok(StructData { name, variant_data })
     }
 
-    pub fn name(&self) -> Option<&SmolStr> {
+    pub fn name(&self) -> Option<&Name> {
         self.name.as_ref()
     }
 
@@ -62,6 +73,69 @@ impl StructData {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Union {
+    def_id: DefId,
+}
+
+impl Union {
+    pub(crate) fn new(def_id: DefId) -> Self {
+        Union { def_id }
+    }
+
+    pub fn def_id(&self) -> DefId {
+        self.def_id
+    }
+
+    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<Name>> {
+        Ok(db.union_data(self.def_id)?.name.clone())
+    }
+
+    pub fn variant_data(&self, db: &impl HirDatabase) -> Cancelable<Arc<VariantData>> {
+        Ok(db.union_data(self.def_id)?.variant_data.clone())
+    }
+
+    pub fn field(&self, db: &impl HirDatabase, name: &Name) -> Cancelable<Option<StructField>> {
+        let data = self.variant_data(db)?;
+        let owner = VariantId::Union(*self);
+        Ok(data.field(name).map(|idx| StructField::new(owner, idx)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnionData {
+    name: Option<Name>,
+    variant_data: Arc<VariantData>,
+}
+
+impl UnionData {
+    pub(crate) fn new(
+        _db: &impl HirDatabase,
+        _module: &Module,
+        union_def: ast::UnionDef,
+    ) -> Cancelable<Self> {
+        let name = union_def.name().map(|n| n.as_name());
+        // A union's fields are always named and all start at offset zero, so
+        // the same `Struct` shape used for named-field structs is reused
+        // here; there is no disjointness between variants to model.
+        let variant_data = union_def
+            .named_field_def_list()
+            .map(VariantData::new_named)
+            .unwrap_or(VariantData::Unit);
+        let variant_data = Arc::new(variant_data);
+        Ok(UnionData { name, variant_data })
+    }
+
+    pub fn name(&self) -> Option<&Name> {
+        self.name.as_ref()
+    }
+
+    pub fn variant_data(&self) -> &Arc<VariantData> {
+        &self.variant_data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Enum {
     def_id: DefId,
 }
@@ -75,35 +149,40 @@ impl Enum {
         self.def_id
     }
 
-    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<SmolStr>> {
+    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<Name>> {
         Ok(db.enum_data(self.def_id)?.name.clone())
     }
+
+    pub fn variants(&self, db: &impl HirDatabase) -> Cancelable<Vec<EnumVariant>> {
+        let variants = db.enum_data(self.def_id)?;
+        Ok((0..variants.variants.len())
+            .map(|idx| EnumVariant::new(*self, idx))
+            .collect())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumData {
-    name: Option<SmolStr>,
-    variants: Vec<(SmolStr, Arc<VariantData>)>,
+    name: Option<Name>,
+    variants: Vec<(Option<Name>, Arc<VariantData>)>,
 }
 
 impl EnumData {
     pub(crate) fn new(
-        db: &impl HirDatabase,
-        module: &Module,
+        _db: &impl HirDatabase,
+        _module: &Module,
         enum_def: ast::EnumDef,
     ) -> Cancelable<Self> {
-        let name = enum_def.name().map(|n| n.text());
+        let name = enum_def.name().map(|n| n.as_name());
         let variants = if let Some(evl) = enum_def.variant_list() {
             evl.variants()
                 .map(|v| {
-                    Ok((
-                        v.name()
-                            .map(|n| n.text())
-                            .unwrap_or_else(|| SmolStr::new("[error]")),
-                        Arc::new(VariantData::new(db, module, v.flavor())?),
-                    ))
+                    (
+                        v.name().map(|n| n.as_name()),
+                        Arc::new(VariantData::new(v.flavor())),
+                    )
                 })
-                .collect::<Cancelable<_>>()?
+                .collect()
         } else {
             Vec::new()
         };
@@ -111,73 +190,169 @@ impl EnumData {
     }
 }
 
-/// A single field of an enum variant or struct
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single variant of an `Enum`, identified by the enum it belongs to and
+/// its position among the enum's variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnumVariant {
+    parent: Enum,
+    idx: usize,
+}
+
+impl EnumVariant {
+    pub(crate) fn new(parent: Enum, idx: usize) -> Self {
+        EnumVariant { parent, idx }
+    }
+
+    pub fn parent_enum(&self) -> Enum {
+        self.parent
+    }
+
+    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<Name>> {
+        let data = db.enum_data(self.parent.def_id())?;
+        Ok(data.variants.get(self.idx).and_then(|(name, _)| name.clone()))
+    }
+
+    pub fn variant_data(&self, db: &impl HirDatabase) -> Cancelable<Arc<VariantData>> {
+        let data = db.enum_data(self.parent.def_id())?;
+        Ok(data
+            .variants
+            .get(self.idx)
+            .map(|(_, variant_data)| variant_data.clone())
+            .unwrap_or_else(|| Arc::new(VariantData::Unit)))
+    }
+
+    pub fn field(&self, db: &impl HirDatabase, name: &Name) -> Cancelable<Option<StructField>> {
+        let data = self.variant_data(db)?;
+        let owner = VariantId::EnumVariant(*self);
+        Ok(data.field(name).map(|idx| StructField::new(owner, idx)))
+    }
+}
+
+/// Identifies whichever `Struct`, `Union` or enum variant a `VariantData`
+/// belongs to. This is the "owner" half of a `StructField`'s
+/// (owner, position) key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariantId {
+    Struct(Struct),
+    Union(Union),
+    EnumVariant(EnumVariant),
+}
+
+impl VariantId {
+    fn variant_data(self, db: &impl HirDatabase) -> Cancelable<Arc<VariantData>> {
+        match self {
+            VariantId::Struct(s) => s.variant_data(db),
+            VariantId::Union(u) => u.variant_data(db),
+            VariantId::EnumVariant(ev) => ev.variant_data(db),
+        }
+    }
+}
+
+/// A single field of a struct, union or enum variant, identified by its
+/// owner and its position among the owner's fields. Unlike a raw name
+/// lookup, this also covers tuple-field access (`foo.0`): the field's
+/// `Name` may itself be a numeric tuple-field name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StructField {
-    name: SmolStr,
-    ty: Ty,
+    parent: VariantId,
+    idx: usize,
 }
 
 impl StructField {
-    pub fn name(&self) -> SmolStr {
-        self.name.clone()
+    pub(crate) fn new(parent: VariantId, idx: usize) -> Self {
+        StructField { parent, idx }
+    }
+
+    pub fn name(&self, db: &impl HirDatabase) -> Cancelable<Option<Name>> {
+        let data = self.parent.variant_data(db)?;
+        Ok(data.field_data().get(self.idx).and_then(|f| f.name.clone()))
+    }
+
+    pub fn type_ref(&self, db: &impl HirDatabase) -> Cancelable<TypeRef> {
+        let data = self.parent.variant_data(db)?;
+        Ok(data
+            .field_data()
+            .get(self.idx)
+            .map(|f| f.type_ref.clone())
+            .unwrap_or(TypeRef::Placeholder))
     }
-    pub fn ty(&self) -> Ty {
-        self.ty.clone()
+
+    pub fn ty(&self, db: &impl HirDatabase) -> Cancelable<Ty> {
+        Ok(db.field_type(self.parent, self.idx)?)
     }
 }
 
+/// The syntactic data stored for a single field: its name (if any) and its
+/// unresolved `TypeRef`. Kept separate from the `StructField` handle so
+/// that `VariantData::new` stays pure syntax lowering with no knowledge of
+/// which `Struct`/`Union`/`EnumVariant` will end up owning it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldData {
+    name: Option<Name>,
+    type_ref: TypeRef,
+}
+
 /// Fields of an enum variant or struct
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VariantData {
-    Struct(Vec<StructField>),
-    Tuple(Vec<StructField>),
+    Struct(Vec<FieldData>),
+    Tuple(Vec<FieldData>),
     Unit,
 }
 
 impl VariantData {
-    pub fn new(db: &impl HirDatabase, module: &Module, flavor: StructFlavor) -> Cancelable<Self> {
-        Ok(match flavor {
+    /// Lowers the fields of a struct/variant flavor to their syntactic
+    /// `TypeRef`s. This is pure syntax-to-syntax lowering: no database type
+    /// query runs here, so `struct_data`/`enum_data` no longer get
+    /// invalidated by edits that only affect type inference.
+    pub fn new(flavor: StructFlavor) -> Self {
+        match flavor {
             StructFlavor::Tuple(fl) => {
                 let fields = fl
                     .fields()
                     .enumerate()
-                    .map(|(i, fd)| {
-                        Ok(StructField {
-                            name: SmolStr::new(i.to_string()),
-                            ty: Ty::from_ast_opt(db, &module, fd.type_ref())?,
-                        })
+                    .map(|(i, fd)| FieldData {
+                        name: Some(Name::tuple_field_name(i)),
+                        type_ref: TypeRef::from_ast_opt(fd.type_ref()),
                     })
-                    .collect::<Cancelable<_>>()?;
+                    .collect();
                 VariantData::Tuple(fields)
             }
-            StructFlavor::Named(fl) => {
-                let fields = fl
-                    .fields()
-                    .map(|fd| {
-                        Ok(StructField {
-                            name: fd
-                                .name()
-                                .map(|n| n.text())
-                                .unwrap_or_else(|| SmolStr::new("[error]")),
-                            ty: Ty::from_ast_opt(db, &module, fd.type_ref())?,
-                        })
-                    })
-                    .collect::<Cancelable<_>>()?;
-                VariantData::Struct(fields)
-            }
+            StructFlavor::Named(fl) => Self::new_named(fl),
             StructFlavor::Unit => VariantData::Unit,
-        })
+        }
+    }
+
+    fn new_named(fl: ast::NamedFieldDefList) -> Self {
+        let fields = fl
+            .fields()
+            .map(|fd| FieldData {
+                name: fd.name().map(|n| n.as_name()),
+                type_ref: TypeRef::from_ast_opt(fd.type_ref()),
+            })
+            .collect();
+        VariantData::Struct(fields)
+    }
+
+    /// Looks up a field by name, accepting either a named field or a
+    /// numeric tuple-field `Name` (`foo.bar` vs `foo.0`), and returns its
+    /// position among `field_data()`.
+    pub(crate) fn field(&self, name: &Name) -> Option<usize> {
+        self.field_data().iter().position(|f| f.name.as_ref() == Some(name))
     }
 
-    pub(crate) fn get_field_ty(&self, field_name: &str) -> Option<Ty> {
-        self.fields()
-            .iter()
-            .find(|f| f.name == field_name)
-            .map(|f| f.ty.clone())
+    /// Enumerates all fields as `StructField` handles, for callers (e.g.
+    /// completion) that need to list a type's fields rather than resolve
+    /// one by name. `owner` identifies which `Struct`/`Union`/`EnumVariant`
+    /// this `VariantData` came from, since the handles need it to look
+    /// themselves back up through `db`.
+    pub fn fields(&self, owner: VariantId) -> Vec<StructField> {
+        (0..self.field_data().len())
+            .map(|idx| StructField::new(owner, idx))
+            .collect()
     }
 
-    pub fn fields(&self) -> &[StructField] {
+    fn field_data(&self) -> &[FieldData] {
         match *self {
             VariantData::Struct(ref fields) | VariantData::Tuple(ref fields) => fields,
             _ => &[],
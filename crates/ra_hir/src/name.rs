@@ -0,0 +1,69 @@
+use std::fmt;
+
+use ra_syntax::{ast, SmolStr};
+
+/// `Name` is a wrapper around string, which is used in hir for both references
+/// and declarations. Unlike a plain `SmolStr`, a `Name` is normalized at
+/// construction time: raw identifiers lose their `r#` prefix (so `r#async`
+/// and `async` compare equal) and tuple-field positions are represented as a
+/// numeric variant rather than a stringified index.
+///
+/// In theory, names should also carry hygiene info, but we are not there yet!
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Name(Repr);
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Repr {
+    Text(SmolStr),
+    TupleField(usize),
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Repr::Text(text) => fmt::Display::fmt(text, f),
+            Repr::TupleField(idx) => fmt::Display::fmt(idx, f),
+        }
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for Repr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repr::Text(text) => fmt::Debug::fmt(text, f),
+            Repr::TupleField(idx) => fmt::Debug::fmt(idx, f),
+        }
+    }
+}
+
+impl Name {
+    fn new(text: SmolStr) -> Name {
+        Name(Repr::Text(text))
+    }
+
+    /// Builds the name of a tuple field or tuple-struct field at `idx`
+    /// (`foo.0`), so that callers outside this crate can look up a field by
+    /// numeric index through the same `Name`-keyed API used for named
+    /// fields (`Struct::field`, `EnumVariant::field`, ...).
+    pub fn tuple_field_name(idx: usize) -> Name {
+        Name(Repr::TupleField(idx))
+    }
+}
+
+pub trait AsName {
+    fn as_name(&self) -> Name;
+}
+
+impl AsName for ast::Name {
+    fn as_name(&self) -> Name {
+        let text = self.text();
+        let raw_text = text.as_str().strip_prefix("r#").unwrap_or(text.as_str());
+        Name::new(SmolStr::new(raw_text))
+    }
+}
@@ -0,0 +1,71 @@
+use ra_syntax::ast::{self, TypeRefKind};
+
+use crate::path::Path;
+
+/// Compact representation of a type, as directly expressed in syntax.
+///
+/// This is a purely syntactic description — no database access, no name
+/// resolution — of a written type annotation. It is what each field's
+/// `FieldData` stores, so that building `VariantData` never touches type inference;
+/// the actual `Ty` is computed from a `TypeRef` lazily, by the
+/// `HirDatabase::field_type` query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeRef {
+    Never,
+    Placeholder,
+    Path(Path),
+    RawPtr(Mutability, Box<TypeRef>),
+    Reference(Mutability, Box<TypeRef>),
+    Array(Box<TypeRef>),
+    Slice(Box<TypeRef>),
+    Tuple(Vec<TypeRef>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutability {
+    Shared,
+    Mut,
+}
+
+impl TypeRef {
+    /// Converts an `ast::TypeRef` into a `TypeRef`, lowering child type refs
+    /// recursively. Missing or unrecognized syntax lowers to `Placeholder`
+    /// rather than failing, matching the error-recovery style used
+    /// elsewhere when walking possibly-incomplete syntax trees.
+    pub(crate) fn from_ast(node: ast::TypeRef) -> Self {
+        match node.kind() {
+            TypeRefKind::PathType(inner) => inner
+                .path()
+                .and_then(Path::from_ast)
+                .map(TypeRef::Path)
+                .unwrap_or(TypeRef::Placeholder),
+            TypeRefKind::TupleType(inner) => {
+                TypeRef::Tuple(inner.fields().map(TypeRef::from_ast).collect())
+            }
+            TypeRefKind::NeverType(..) => TypeRef::Never,
+            TypeRefKind::PointerType(inner) => {
+                let mutability = if inner.is_mut() { Mutability::Mut } else { Mutability::Shared };
+                TypeRef::RawPtr(mutability, Box::new(TypeRef::from_ast_opt(inner.type_ref())))
+            }
+            TypeRefKind::ReferenceType(inner) => {
+                let mutability = if inner.is_mut() { Mutability::Mut } else { Mutability::Shared };
+                TypeRef::Reference(mutability, Box::new(TypeRef::from_ast_opt(inner.type_ref())))
+            }
+            TypeRefKind::ArrayType(inner) => {
+                TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
+            }
+            TypeRefKind::SliceType(inner) => {
+                TypeRef::Slice(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
+            }
+            _ => TypeRef::Placeholder,
+        }
+    }
+
+    pub(crate) fn from_ast_opt(node: Option<ast::TypeRef>) -> Self {
+        if let Some(node) = node {
+            TypeRef::from_ast(node)
+        } else {
+            TypeRef::Placeholder
+        }
+    }
+}